@@ -16,6 +16,118 @@ impl ContentLength {
     }
 }
 
+/// Where the chunk scanner currently is within a `Transfer-Encoding: chunked`
+/// body.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkPhase {
+    /// Reading the `<hex-size>[;chunk-ext]\r\n` line.
+    #[default]
+    Size,
+    /// Consuming the `remaining` data bytes of the current chunk.
+    Data,
+    /// Consuming the `\r\n` that trails a chunk's data.
+    Crlf,
+    /// Past the zero-length chunk, skipping optional trailer lines until the
+    /// final blank line.
+    Trailer,
+}
+
+/// A resumable scanner for `Transfer-Encoding: chunked` bodies. It is advanced
+/// one buffer at a time by [`ChunkState::scan`] and persists between
+/// `modify_stream` calls so a chunk split across two reads resumes correctly.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+struct ChunkState {
+    phase: ChunkPhase,
+    /// Data bytes still expected in the current chunk.
+    remaining: usize,
+    /// The hex size accumulated so far for the current size line.
+    size: usize,
+    /// Whether we are past the `;` that begins a chunk extension.
+    in_ext: bool,
+    /// Whether the trailer line seen so far is empty (so the next `\n` ends the
+    /// body).
+    trailer_empty: bool,
+}
+
+impl ChunkState {
+    /// Feeds `body` through the chunk state machine, returning `true` once the
+    /// terminating zero-length chunk (and any trailers) have been observed.
+    fn scan(&mut self, body: &[u8]) -> bool {
+        let mut i = 0;
+        while i < body.len() {
+            match self.phase {
+                ChunkPhase::Size => {
+                    let byte = body[i];
+                    if self.in_ext {
+                        if byte == b'\n' {
+                            self.in_ext = false;
+                            if self.finish_size_line() {
+                                return true;
+                            }
+                        }
+                    } else if byte == b';' {
+                        self.in_ext = true;
+                    } else if byte == b'\n' {
+                        if self.finish_size_line() {
+                            return true;
+                        }
+                    } else if byte != b'\r' {
+                        if let Some(digit) = hex_val(byte) {
+                            self.size = self.size.wrapping_mul(16).wrapping_add(digit as usize);
+                        }
+                    }
+                    i += 1;
+                }
+                ChunkPhase::Data => {
+                    let take = self.remaining.min(body.len() - i);
+                    self.remaining -= take;
+                    i += take;
+                    if self.remaining == 0 {
+                        self.phase = ChunkPhase::Crlf;
+                    }
+                }
+                ChunkPhase::Crlf => {
+                    // Tolerantly consume the `\r\n` that follows chunk data.
+                    if body[i] == b'\n' {
+                        self.size = 0;
+                        self.phase = ChunkPhase::Size;
+                    }
+                    i += 1;
+                }
+                ChunkPhase::Trailer => {
+                    let byte = body[i];
+                    if byte == b'\n' {
+                        if self.trailer_empty {
+                            return true;
+                        }
+                        self.trailer_empty = true;
+                    } else if byte != b'\r' {
+                        self.trailer_empty = false;
+                    }
+                    i += 1;
+                }
+            }
+        }
+        false
+    }
+
+    /// Handles the end of a size line, moving to the data or trailer state.
+    /// Always returns `false`; a zero-length chunk parks in [`ChunkPhase::Trailer`]
+    /// and the body is only marked finished once the terminating blank line is
+    /// seen there.
+    fn finish_size_line(&mut self) -> bool {
+        if self.size == 0 {
+            self.phase = ChunkPhase::Trailer;
+            self.trailer_empty = true;
+            false
+        } else {
+            self.remaining = self.size;
+            self.phase = ChunkPhase::Data;
+            false
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Protocol {
     Http,
@@ -90,11 +202,32 @@ pub struct Parser {
     past_host: bool,
     /// (bytes past heading, content length)
     content_len: Option<ContentLength>,
+    /// Set once the `\r\n\r\n` heading terminator has been seen, so later
+    /// buffers are treated as pure body bytes.
+    heading_terminated: bool,
+    /// The chunk scanner, present when the request used
+    /// `Transfer-Encoding: chunked` instead of a `Content-Length`.
+    chunked: Option<ChunkState>,
+    /// Set when the request heading carried `Expect: 100-continue`, so the
+    /// caller can emit an interim [`CONTINUE`] response before reading the
+    /// body.
+    pub expects_continue: bool,
     pub finished: bool,
     pub info: Option<RequestInfo>,
 }
 
 impl Parser {
+    /// The interim response to write back to the client before reading the
+    /// body, or `None` when the request made no `Expect: 100-continue` demand.
+    /// Returns the raw [`CONTINUE`] bytes so the caller can relay them verbatim.
+    pub const fn continue_response(&self) -> Option<&'static [u8]> {
+        if self.expects_continue {
+            Some(CONTINUE)
+        } else {
+            None
+        }
+    }
+
     #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
     pub fn modify_stream(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
         if self.finished {
@@ -158,40 +291,51 @@ impl Parser {
             };
         }
 
-        macro_rules! remove_loop {
-            () => {
-                if let Some(byte) = remove!() {
-                    byte
+        macro_rules! b_else_err {
+            ($b:literal, $e:expr) => {
+                if let Some(b) = next!().and_then(|b| if b == $b { Some(b) } else { None }) {
+                    b
                 } else {
-                    break;
+                    return Err($e);
                 }
             };
         }
 
-        macro_rules! remove_iter_loop {
-            ($var:ident => $body:block) => {
-                loop {
-                    let $var = remove_loop!();
-                    $body
+        /// Like `remove!`, but transparently decodes a `%XX` escape into the
+        /// byte it represents. A truncated escape at the buffer boundary or an
+        /// invalid hex digit is rejected with `Error::InvalidRequest`.
+        macro_rules! remove_decoded {
+            () => {
+                match remove!() {
+                    Some(b'%') => match (remove!().and_then(hex_val), remove!().and_then(hex_val)) {
+                        (Some(hi), Some(lo)) => Some(hi * 16 + lo),
+                        _ => return Err(Error::InvalidRequest),
+                    },
+                    other => other,
                 }
             };
         }
 
-        macro_rules! b_else_err {
-            ($b:literal, $e:expr) => {
-                if let Some(b) = next!().and_then(|b| if b == $b { Some(b) } else { None }) {
-                    b
-                } else {
-                    return Err($e);
+        /// Like `peek!`, but decoding a `%XX` escape. Yields `None` for a
+        /// truncated or invalid escape so callers can fall through.
+        macro_rules! peek_decoded {
+            () => {
+                match peek!() {
+                    Some(b'%') => match (
+                        buf.get(idx + 1).copied().and_then(hex_val),
+                        buf.get(idx + 2).copied().and_then(hex_val),
+                    ) {
+                        (Some(hi), Some(lo)) => Some(hi * 16 + lo),
+                        _ => None,
+                    },
+                    other => other,
                 }
             };
         }
 
-        macro_rules! remove_b_else_err {
+        macro_rules! remove_decoded_b_else_err {
             ($b:literal, $e:expr) => {
-                if let Some(b) = remove!().and_then(|b| if b == $b { Some(b) } else { None }) {
-                    b
-                } else {
+                if remove_decoded!() != Some($b) {
                     return Err($e);
                 }
             };
@@ -215,45 +359,49 @@ impl Parser {
                 remove!();
             }
 
-            // get the protocol
-            remove_b_else_err!(b'h', Error::InvalidProtocol);
-            remove_b_else_err!(b't', Error::InvalidProtocol);
-            remove_b_else_err!(b't', Error::InvalidProtocol);
-            remove_b_else_err!(b'p', Error::InvalidProtocol);
+            // get the protocol, decoding %XX escapes so a percent-encoded
+            // target URL still parses
+            remove_decoded_b_else_err!(b'h', Error::InvalidProtocol);
+            remove_decoded_b_else_err!(b't', Error::InvalidProtocol);
+            remove_decoded_b_else_err!(b't', Error::InvalidProtocol);
+            remove_decoded_b_else_err!(b'p', Error::InvalidProtocol);
 
-            info.protocol = if peek!() == Some(b's') {
-                remove!();
+            info.protocol = if peek_decoded!() == Some(b's') {
+                remove_decoded!();
                 Protocol::Https
             } else {
                 Protocol::Http
             };
 
             // skip the ://
-            remove_b_else_err!(b':', Error::InvalidProtocol);
-            remove_b_else_err!(b'/', Error::InvalidProtocol);
-            remove_b_else_err!(b'/', Error::InvalidProtocol);
+            remove_decoded_b_else_err!(b':', Error::InvalidProtocol);
+            remove_decoded_b_else_err!(b'/', Error::InvalidProtocol);
+            remove_decoded_b_else_err!(b'/', Error::InvalidProtocol);
 
-            // get the domain and strip the domain from the buffer
+            // get the domain and strip the domain from the buffer, decoding the
+            // scheme/host/port escapes while leaving the path bytes untouched
             let mut addr = String::new();
             let mut port = String::<5>::new();
-            remove_iter_loop!(byte => {
+            loop {
+                let Some(byte) = remove_decoded!() else { break };
                 if byte == b'/' {
                     break;
                 } else if byte == b' ' {
                     return Err(Error::MissingPath);
                 } else if byte == b':' {
-                    remove_iter_loop!(byte => {
+                    loop {
+                        let Some(byte) = remove_decoded!() else { break };
                         if byte == b'/' {
                             break;
                         } else if byte == b' ' {
                             return Err(Error::MissingPath);
                         }
                         port.push(byte as _).map_err(|_| Error::InvalidPort)?;
-                    });
+                    }
                     break;
                 }
                 addr.push(byte as _).map_err(|_| Error::TooLong)?;
-            });
+            }
             info.addr = addr;
 
             let port = port.parse().unwrap_or_else(|_| unsafe {
@@ -307,14 +455,37 @@ impl Parser {
             }
             removed += r;
         }
+        let prev_terminated = self.heading_terminated;
         let heading_end = find(&buf[idx..], b"\r\n\r\n").map_or(0, |i| i + 4);
 
-        self.get_content_len(&mut buf[idx..], heading_end)?;
-
-        if (heading_end == 0 && self.content_len.map_or(false, ContentLength::full))
-            || (heading_end != 0 && self.content_len.is_none())
+        // Detect chunked transfer encoding while the heading is still in view.
+        if heading_end != 0 && !prev_terminated && header_block_is_chunked(&buf[idx..idx + heading_end])
         {
-            self.finished = true;
+            self.chunked = Some(ChunkState::default());
+        }
+        if heading_end != 0 && !prev_terminated {
+            self.expects_continue = header_block_expects_continue(&buf[idx..idx + heading_end]);
+        }
+        if heading_end != 0 {
+            self.heading_terminated = true;
+        }
+
+        if let Some(mut state) = self.chunked {
+            // Once the heading is behind us the whole buffer is body; on the
+            // buffer that carried the heading, the body starts after it.
+            let body_start = if prev_terminated { idx } else { idx + heading_end };
+            if state.scan(&buf[body_start..]) {
+                self.finished = true;
+            }
+            self.chunked = Some(state);
+        } else {
+            self.get_content_len(&mut buf[idx..], heading_end)?;
+
+            if (heading_end == 0 && self.content_len.map_or(false, ContentLength::full))
+                || (heading_end != 0 && self.content_len.is_none())
+            {
+                self.finished = true;
+            }
         }
 
         self.info = Some(info);
@@ -325,11 +496,9 @@ impl Parser {
     fn replace_host_header(buf: &mut [u8], info: &RequestInfo) -> Result<usize, Error> {
         let mut removed = 0;
 
-        let host_str = b"Host: ";
-        let Some(host_idx) = find(buf, host_str) else {
+        let Some(mut idx) = find_header_value(buf, b"host") else {
             return Ok(0);
         };
-        let mut idx = host_idx + host_str.len();
 
         let len_of_old_host = memchr::memchr(b'\r', &buf[idx..]).ok_or(Error::InvalidRequest)?;
 
@@ -376,11 +545,9 @@ impl Parser {
     }
 
     fn get_content_len(&mut self, buf: &mut [u8], heading_end: usize) -> Result<(), Error> {
-        let content_len_str = b"\nContent-Length: ";
-        let Some(host_idx) = find(buf, content_len_str) else {
+        let Some(idx) = find_header_value(buf, b"content-length") else {
             return Ok(());
         };
-        let idx = host_idx + content_len_str.len();
 
         let mut content_len = self.content_len.unwrap_or_default();
         let end = memchr::memchr(b'\r', &buf[idx..]).ok_or(Error::InvalidRequest)?;
@@ -415,6 +582,81 @@ pub fn modify_response(response: &mut [u8]) -> bool {
     true
 }
 
+/// Whether a heading block declares `Transfer-Encoding: chunked`. The field
+/// name and value are matched ignoring ASCII case.
+fn header_block_is_chunked(heading: &[u8]) -> bool {
+    let Some(start) = find_header_value(heading, b"transfer-encoding") else {
+        return false;
+    };
+    let value = &heading[start..];
+    let end = memchr::memchr(b'\n', value).unwrap_or(value.len());
+    find_ci(&value[..end], b"chunked").is_some()
+}
+
+/// Locates a header by field name (matched ignoring ASCII case) in a heading
+/// block and returns the index of the start of its value, skipping any
+/// whitespace after the `:`. Scanning stops at the blank line that ends the
+/// headers, so body bytes are never matched.
+fn find_header_value(buf: &[u8], name: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let line_end = memchr::memchr(b'\n', &buf[pos..]).map_or(buf.len() - pos, |i| i);
+        let line = &buf[pos..pos + line_end];
+        if line.is_empty() || line == b"\r" {
+            return None;
+        }
+        if let Some(colon) = memchr::memchr(b':', line) {
+            if line[..colon].eq_ignore_ascii_case(name) {
+                let mut value = colon + 1;
+                while matches!(line.get(value), Some(b' ' | b'\t')) {
+                    value += 1;
+                }
+                return Some(pos + value);
+            }
+        }
+        pos += line_end + 1;
+    }
+    None
+}
+
+/// The interim response a caller should write back to the client when the
+/// request declared `Expect: 100-continue`, before it starts reading the body.
+pub const CONTINUE: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+/// Whether a heading block declares `Expect: 100-continue`. The field name and
+/// value are matched ignoring ASCII case.
+fn header_block_expects_continue(heading: &[u8]) -> bool {
+    let Some(start) = find_header_value(heading, b"expect") else {
+        return false;
+    };
+    let value = &heading[start..];
+    let end = memchr::memchr(b'\n', value).unwrap_or(value.len());
+    find_ci(&value[..end], b"100-continue").is_some()
+}
+
+/// A case-insensitive (ASCII) variant of [`memchr::memmem::find`].
+fn find_ci(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    (0..=haystack.len() - needle.len()).find(|&i| {
+        haystack[i..i + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    })
+}
+
+/// Parses a single hexadecimal ASCII digit.
+const fn hex_val(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
 /// Removes a number of elements from a slice.
 fn remove_n_from_slice(slice: &mut [u8], index: usize, n: usize) {
     let len = slice.len();
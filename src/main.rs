@@ -7,7 +7,47 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 #[derive(Clone)]
-struct CORSProxy(Arc<Client<HttpsConnector<HttpConnector>>>);
+struct CORSProxy {
+    client: Arc<Client<HttpsConnector<HttpConnector>>>,
+    /// Origins allowed to receive a reflected `Access-Control-Allow-Origin`.
+    /// Any request whose `Origin` is not listed here is served without
+    /// credentialed CORS headers.
+    allowed_origins: Arc<Vec<String>>,
+}
+
+impl CORSProxy {
+    /// Whether `origin` is permitted to receive a credentialed CORS response.
+    fn origin_allowed(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|o| o == origin)
+    }
+
+    /// Builds the `204 No Content` answer to a CORS preflight, reflecting the
+    /// requested method and headers and the allowlisted origin.
+    fn preflight_response(&self, req: &hyper::Request<Body>) -> hyper::Response<Body> {
+        let mut res = hyper::Response::new(Body::empty());
+        *res.status_mut() = hyper::StatusCode::NO_CONTENT;
+
+        let headers = res.headers_mut();
+        if let Some(method) = req.headers().get("Access-Control-Request-Method") {
+            headers.insert("Access-Control-Allow-Methods", method.clone());
+        }
+        if let Some(req_headers) = req.headers().get("Access-Control-Request-Headers") {
+            headers.insert("Access-Control-Allow-Headers", req_headers.clone());
+        }
+
+        let origin = req
+            .headers()
+            .get("Origin")
+            .and_then(|v| v.to_str().ok())
+            .filter(|o| self.origin_allowed(o));
+        if let Some(origin) = origin {
+            headers.insert("Access-Control-Allow-Origin", origin.parse().unwrap());
+            headers.insert("Access-Control-Allow-Credentials", "true".parse().unwrap());
+        }
+
+        res
+    }
+}
 
 impl tower::Service<hyper::Request<Body>> for CORSProxy {
     type Response = hyper::Response<Body>;
@@ -19,8 +59,18 @@ impl tower::Service<hyper::Request<Body>> for CORSProxy {
     }
 
     fn call(&mut self, mut req: hyper::Request<Body>) -> Self::Future {
-        let client = self.0.clone();
+        let client = self.client.clone();
+        let proxy = self.clone();
         Box::pin(async move {
+            // Answer CORS preflights ourselves instead of forwarding them: the
+            // upstream gains nothing from an `OPTIONS` it can't CORS-annotate,
+            // and the extra round trip only adds latency.
+            if req.method() == hyper::Method::OPTIONS
+                && req.headers().contains_key("Access-Control-Request-Method")
+            {
+                return Ok(proxy.preflight_response(&req));
+            }
+
             *req.uri_mut() = dbg!(req.uri().path().strip_prefix('/').unwrap().parse().unwrap());
             let host = req.uri().host().unwrap().to_owned();
             // set the host header
@@ -33,25 +83,29 @@ impl tower::Service<hyper::Request<Body>> for CORSProxy {
                 .map(String::from);
 
             println!("{:?}", req_origin);
+
+            // Any `Expect: 100-continue` header is forwarded as is and nothing
+            // extra happens here. The waiting client gets its `100 Continue`
+            // from hyper's server side, which writes it when the request body
+            // is first polled, i.e. once the client below starts sending the
+            // body upstream; an upstream's own `100 Continue` is not passed on.
+            // That interim never carries CORS headers and does not affect
+            // body-completion tracking.
             let proxy_res = client.request(req).await.unwrap();
 
             println!("got");
 
             let mut res = hyper::Response::new(Body::empty());
-            // forwards the headers
-            *res.headers_mut() = proxy_res.headers().clone();
+            // forwards the status and headers, preserving the upstream
+            // Content-Length/Transfer-Encoding
             *res.status_mut() = proxy_res.status();
+            *res.headers_mut() = proxy_res.headers().clone();
 
-            *res.body_mut() = hyper::body::to_bytes(proxy_res).await.unwrap().into();
+            // stream the body through untouched so bytes flow incrementally
+            // instead of being buffered in full
+            *res.body_mut() = proxy_res.into_body();
 
-            // add CORS headers (Access-Control-Allow-Origin, Access-Control-Allow-Methods, Access-Control-Allow-Headers, Access-Control-Allow-Credentials)
-            res.headers_mut().insert(
-                "Access-Control-Allow-Origin",
-                req_origin
-                    .unwrap_or_else(|| String::from("*"))
-                    .parse()
-                    .unwrap(),
-            );
+            // add CORS headers (Access-Control-Allow-Methods, Access-Control-Allow-Headers)
             res.headers_mut().insert(
                 "Access-Control-Allow-Methods",
                 "GET, POST, OPTIONS".parse().unwrap(),
@@ -60,8 +114,21 @@ impl tower::Service<hyper::Request<Body>> for CORSProxy {
                 "Access-Control-Allow-Headers",
                 "Content-Type, *".parse().unwrap(),
             );
-            res.headers_mut()
-                .insert("Access-Control-Allow-Credentials", "true".parse().unwrap());
+
+            // Only reflect the Origin (with credentials) when it is allowlisted.
+            // A reflected origin paired with credentials would otherwise defeat
+            // the same-origin protection entirely, so unmatched origins get
+            // neither header.
+            if req_origin.as_deref().is_some_and(|o| proxy.origin_allowed(o)) {
+                let origin = req_origin.unwrap();
+                res.headers_mut()
+                    .insert("Access-Control-Allow-Origin", origin.parse().unwrap());
+                res.headers_mut()
+                    .insert("Access-Control-Allow-Credentials", "true".parse().unwrap());
+            } else {
+                res.headers_mut().remove("Access-Control-Allow-Origin");
+                res.headers_mut().remove("Access-Control-Allow-Credentials");
+            }
 
             println!("{:?}", res);
 
@@ -72,9 +139,20 @@ impl tower::Service<hyper::Request<Body>> for CORSProxy {
 
 #[shuttle_runtime::main]
 async fn tower() -> shuttle_tower::ShuttleTower<CORSProxy> {
-    let service = CORSProxy(Arc::new(
-        Client::builder().build::<_, hyper::Body>(HttpsConnector::new()),
-    ));
+    let allowed_origins = std::env::var("ALLOWED_ORIGINS")
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let service = CORSProxy {
+        client: Arc::new(Client::builder().build::<_, hyper::Body>(HttpsConnector::new())),
+        allowed_origins: Arc::new(allowed_origins),
+    };
 
     Ok(service.into())
 }